@@ -0,0 +1,366 @@
+use crate::{BinaryOp, Expr, Hir, LocalDef, RuntimeError, Stmt};
+use arena::Id;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+	PushConst = 0,
+	LoadLocal = 1,
+	StoreLocal = 2,
+	Add = 3,
+	Sub = 4,
+	Mul = 5,
+	Div = 6,
+	Mod = 7,
+	BitAnd = 8,
+	BitOr = 9,
+	BitXor = 10,
+	Shl = 11,
+	Shr = 12,
+	Eq = 13,
+	NEq = 14,
+	Lt = 15,
+	Gt = 16,
+	LtEq = 17,
+	GtEq = 18,
+	And = 19,
+	Or = 20,
+	Call = 21,
+	Return = 22,
+}
+
+impl Op {
+	fn from_byte(byte: u8) -> Option<Op> {
+		Some(match byte {
+			0 => Op::PushConst,
+			1 => Op::LoadLocal,
+			2 => Op::StoreLocal,
+			3 => Op::Add,
+			4 => Op::Sub,
+			5 => Op::Mul,
+			6 => Op::Div,
+			7 => Op::Mod,
+			8 => Op::BitAnd,
+			9 => Op::BitOr,
+			10 => Op::BitXor,
+			11 => Op::Shl,
+			12 => Op::Shr,
+			13 => Op::Eq,
+			14 => Op::NEq,
+			15 => Op::Lt,
+			16 => Op::Gt,
+			17 => Op::LtEq,
+			18 => Op::GtEq,
+			19 => Op::And,
+			20 => Op::Or,
+			21 => Op::Call,
+			22 => Op::Return,
+			_ => return None,
+		})
+	}
+}
+
+impl From<BinaryOp> for Op {
+	fn from(op: BinaryOp) -> Op {
+		match op {
+			BinaryOp::Add => Op::Add,
+			BinaryOp::Sub => Op::Sub,
+			BinaryOp::Mul => Op::Mul,
+			BinaryOp::Div => Op::Div,
+			BinaryOp::Mod => Op::Mod,
+			BinaryOp::BitAnd => Op::BitAnd,
+			BinaryOp::BitOr => Op::BitOr,
+			BinaryOp::BitXor => Op::BitXor,
+			BinaryOp::Shl => Op::Shl,
+			BinaryOp::Shr => Op::Shr,
+			BinaryOp::Eq => Op::Eq,
+			BinaryOp::NEq => Op::NEq,
+			BinaryOp::Lt => Op::Lt,
+			BinaryOp::Gt => Op::Gt,
+			BinaryOp::LtEq => Op::LtEq,
+			BinaryOp::GtEq => Op::GtEq,
+			BinaryOp::And => Op::And,
+			BinaryOp::Or => Op::Or,
+		}
+	}
+}
+
+/// A function table keyed the same way the interpreter resolves calls: by
+/// `(module, item)`, mapping to that function’s compiled instruction stream.
+pub type Functions = HashMap<(String, String), Vec<u8>>;
+
+pub fn compile_program(hirs: &HashMap<String, Hir>) -> Functions {
+	let mut functions = Functions::new();
+	for (module, hir) in hirs {
+		for name in hir.map.keys() {
+			let bytes = compile(hir, name).expect("name came from hir.map");
+			functions.insert((module.clone(), name.clone()), bytes);
+		}
+	}
+	functions
+}
+
+pub fn compile(hir: &Hir, name: &str) -> Option<Vec<u8>> {
+	let body = *hir.map.get(name)?;
+
+	let mut ctx = CodegenCtx { hir, bytes: Vec::new(), slots: HashMap::new() };
+
+	// parameters are assigned slots 0..N, in declaration order, before the
+	// body is compiled, so the argument values `run_bytes` receives for a
+	// call line up with the slots `LoadLocal` expects
+	if let Some(param_ids) = hir.params.get(name) {
+		for param_id in param_ids {
+			ctx.slot(*param_id);
+		}
+	}
+
+	ctx.expr(body);
+	ctx.bytes.push(Op::Return as u8);
+
+	Some(ctx.bytes)
+}
+
+struct CodegenCtx<'a> {
+	hir: &'a Hir,
+	bytes: Vec<u8>,
+	slots: HashMap<Id<LocalDef>, u16>,
+}
+
+impl CodegenCtx<'_> {
+	fn expr(&mut self, expr: Id<Expr>) {
+		match self.hir.exprs.get(expr) {
+			Expr::Missing => {}
+
+			Expr::Integer(n) => {
+				self.bytes.push(Op::PushConst as u8);
+				self.bytes.extend_from_slice(&n.to_le_bytes());
+			}
+
+			Expr::Local(local_def_id) => {
+				let slot = self.slot(*local_def_id);
+				self.bytes.push(Op::LoadLocal as u8);
+				self.bytes.extend_from_slice(&slot.to_le_bytes());
+			}
+
+			Expr::Block(stmts) => {
+				for stmt in stmts {
+					self.stmt(*stmt);
+				}
+			}
+
+			Expr::Call(path, args) => {
+				for arg in args {
+					self.expr(*arg);
+				}
+
+				self.bytes.push(Op::Call as u8);
+				self.bytes.push(args.len() as u8);
+				encode_str(&mut self.bytes, &path.module);
+				encode_str(&mut self.bytes, &path.item);
+			}
+
+			Expr::Binary { lhs, rhs, op } => {
+				self.expr(*lhs);
+				self.expr(*rhs);
+				self.bytes.push(Op::from(*op) as u8);
+			}
+		}
+	}
+
+	fn stmt(&mut self, stmt: Stmt) {
+		match stmt {
+			Stmt::LocalDef(local_def_id) => {
+				let value = self.hir.local_defs.get(local_def_id).value;
+				self.expr(value);
+
+				let slot = self.slot(local_def_id);
+				self.bytes.push(Op::StoreLocal as u8);
+				self.bytes.extend_from_slice(&slot.to_le_bytes());
+			}
+		}
+	}
+
+	fn slot(&mut self, local_def_id: Id<LocalDef>) -> u16 {
+		let next = self.slots.len() as u16;
+		*self.slots.entry(local_def_id).or_insert(next)
+	}
+}
+
+fn encode_str(bytes: &mut Vec<u8>, s: &str) {
+	bytes.push(s.len() as u8);
+	bytes.extend_from_slice(s.as_bytes());
+}
+
+fn decode_str(bytes: &[u8]) -> (&str, usize) {
+	let len = bytes[0] as usize;
+	(std::str::from_utf8(&bytes[1..1 + len]).unwrap(), 1 + len)
+}
+
+pub fn run(
+	functions: &Functions,
+	module: &str,
+	item: &str,
+) -> Result<Option<u32>, RuntimeError> {
+	let bytes = functions
+		.get(&(module.to_string(), item.to_string()))
+		.ok_or(RuntimeError::UndefinedItem)?;
+	run_bytes(functions, bytes, Vec::new())
+}
+
+fn run_bytes(
+	functions: &Functions,
+	bytes: &[u8],
+	mut locals: Vec<u32>,
+) -> Result<Option<u32>, RuntimeError> {
+	let mut stack = Vec::new();
+	let mut i = 0;
+
+	loop {
+		let op = Op::from_byte(bytes[i]).expect("invalid opcode");
+		i += 1;
+
+		match op {
+			Op::PushConst => {
+				let n = u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+				i += 4;
+				stack.push(n);
+			}
+
+			Op::LoadLocal => {
+				let slot =
+					u16::from_le_bytes(bytes[i..i + 2].try_into().unwrap());
+				i += 2;
+				let value = *locals
+					.get(slot as usize)
+					.ok_or(RuntimeError::UnboundLocal)?;
+				stack.push(value);
+			}
+
+			Op::StoreLocal => {
+				let slot =
+					u16::from_le_bytes(bytes[i..i + 2].try_into().unwrap());
+				i += 2;
+				let value = stack.pop().ok_or(RuntimeError::VoidValue)?;
+				if slot as usize == locals.len() {
+					locals.push(value);
+				} else {
+					locals[slot as usize] = value;
+				}
+			}
+
+			Op::Call => {
+				let arg_count = bytes[i] as usize;
+				i += 1;
+				let (module, len) = decode_str(&bytes[i..]);
+				let module = module.to_string();
+				i += len;
+				let (item, len) = decode_str(&bytes[i..]);
+				let item = item.to_string();
+				i += len;
+
+				let callee_locals =
+					stack.split_off(stack.len() - arg_count);
+
+				let callee = functions
+					.get(&(module, item))
+					.ok_or(RuntimeError::UndefinedItem)?;
+				if let Some(value) = run_bytes(functions, callee, callee_locals)?
+				{
+					stack.push(value);
+				}
+			}
+
+			Op::Return => return Ok(stack.pop()),
+
+			binary_op => {
+				let rhs = stack.pop().unwrap();
+				let lhs = stack.pop().unwrap();
+				stack.push(apply(binary_op, lhs, rhs)?);
+			}
+		}
+	}
+}
+
+fn apply(op: Op, lhs: u32, rhs: u32) -> Result<u32, RuntimeError> {
+	Ok(match op {
+		Op::Add => lhs.wrapping_add(rhs),
+		Op::Sub => lhs.wrapping_sub(rhs),
+		Op::Mul => lhs.wrapping_mul(rhs),
+		Op::Div => {
+			if rhs == 0 {
+				return Err(RuntimeError::DivideByZero);
+			}
+			lhs.wrapping_div(rhs)
+		}
+		Op::Mod => {
+			if rhs == 0 {
+				return Err(RuntimeError::ModuloByZero);
+			}
+			lhs.wrapping_rem(rhs)
+		}
+		Op::BitAnd => lhs & rhs,
+		Op::BitOr => lhs | rhs,
+		Op::BitXor => lhs ^ rhs,
+		Op::Shl => lhs.wrapping_shl(rhs),
+		Op::Shr => lhs.wrapping_shr(rhs),
+		Op::Eq => (lhs == rhs) as u32,
+		Op::NEq => (lhs != rhs) as u32,
+		Op::Lt => (lhs < rhs) as u32,
+		Op::Gt => (lhs > rhs) as u32,
+		Op::LtEq => (lhs <= rhs) as u32,
+		Op::GtEq => (lhs >= rhs) as u32,
+		Op::And => ((lhs != 0) && (rhs != 0)) as u32,
+		Op::Or => ((lhs != 0) || (rhs != 0)) as u32,
+		Op::PushConst | Op::LoadLocal | Op::StoreLocal | Op::Call
+		| Op::Return => unreachable!("not a binary op"),
+	})
+}
+
+// also available under `test` so the harness can exercise it without a
+// workspace manifest defining the `disasm` feature
+#[cfg(any(test, feature = "disasm"))]
+pub fn disassemble(bytes: &[u8]) -> String {
+	let mut out = String::new();
+	let mut i = 0;
+
+	while i < bytes.len() {
+		let op = Op::from_byte(bytes[i]).expect("invalid opcode");
+		i += 1;
+
+		match op {
+			Op::PushConst => {
+				let n = u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+				i += 4;
+				writeln!(out, "push.const {n}").unwrap();
+			}
+			Op::LoadLocal => {
+				let slot =
+					u16::from_le_bytes(bytes[i..i + 2].try_into().unwrap());
+				i += 2;
+				writeln!(out, "load.local {slot}").unwrap();
+			}
+			Op::StoreLocal => {
+				let slot =
+					u16::from_le_bytes(bytes[i..i + 2].try_into().unwrap());
+				i += 2;
+				writeln!(out, "store.local {slot}").unwrap();
+			}
+			Op::Call => {
+				let arg_count = bytes[i];
+				i += 1;
+				let (module, len) = decode_str(&bytes[i..]);
+				let module = module.to_string();
+				i += len;
+				let (item, len) = decode_str(&bytes[i..]);
+				writeln!(out, "call {module}.{item}/{arg_count}").unwrap();
+				i += len;
+			}
+			Op::Return => writeln!(out, "return").unwrap(),
+			op => writeln!(out, "{op:?}").unwrap(),
+		}
+	}
+
+	out
+}
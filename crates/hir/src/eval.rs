@@ -0,0 +1,172 @@
+use crate::{BinaryOp, Expr, Hir, LocalDef, Stmt};
+use arena::Id;
+use resolved_index::Path;
+use std::collections::HashMap;
+
+/// The lowered `Hir` for every module, keyed by module name, as produced by
+/// running `lower` over each file in a resolved index.
+pub type Program = HashMap<String, Hir>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+	Void,
+	U32(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuntimeError {
+	UndefinedModule,
+	UndefinedItem,
+	MissingBody,
+	DivideByZero,
+	ModuloByZero,
+	/// a `Local` was read before anything bound it a value — notably, this
+	/// is what a parameter read turns into when its function is run as a
+	/// top-level entry point (`eval`/`run`) rather than called with
+	/// arguments, since only `call`/`Op::Call` bind parameters
+	UnboundLocal,
+	/// a `Value::Void` was used where a `u32` was expected — this is what a
+	/// call to a block-bodied function turns into when its result is used as
+	/// an operand or bound to a variable, since a block always evaluates to
+	/// `Value::Void`
+	VoidValue,
+}
+
+pub fn eval(
+	program: &Program,
+	module: &str,
+	item: &str,
+) -> Result<Value, RuntimeError> {
+	let hir = program.get(module).ok_or(RuntimeError::UndefinedModule)?;
+	let body = *hir.map.get(item).ok_or(RuntimeError::UndefinedItem)?;
+
+	EvalCtx { program, hir, frames: vec![HashMap::new()] }.expr(body)
+}
+
+struct EvalCtx<'a> {
+	program: &'a Program,
+	hir: &'a Hir,
+	frames: Vec<HashMap<Id<LocalDef>, u32>>,
+}
+
+impl<'a> EvalCtx<'a> {
+	fn expr(&mut self, expr: Id<Expr>) -> Result<Value, RuntimeError> {
+		match self.hir.exprs.get(expr) {
+			Expr::Missing => Err(RuntimeError::MissingBody),
+
+			Expr::Integer(n) => Ok(Value::U32(*n)),
+
+			Expr::Local(local_def_id) => {
+				let value = *self
+					.frame()
+					.get(local_def_id)
+					.ok_or(RuntimeError::UnboundLocal)?;
+				Ok(Value::U32(value))
+			}
+
+			Expr::Block(stmts) => {
+				for stmt in stmts {
+					self.stmt(*stmt)?;
+				}
+				Ok(Value::Void)
+			}
+
+			Expr::Call(path, args) => self.call(path, args),
+
+			Expr::Binary { lhs, rhs, op } => {
+				let lhs = self.expr(*lhs)?.expect_u32()?;
+				let rhs = self.expr(*rhs)?.expect_u32()?;
+				Ok(Value::U32(apply(*op, lhs, rhs)?))
+			}
+		}
+	}
+
+	fn stmt(&mut self, stmt: Stmt) -> Result<(), RuntimeError> {
+		match stmt {
+			Stmt::LocalDef(local_def_id) => {
+				let value = self.hir.local_defs.get(local_def_id).value;
+				let value = self.expr(value)?.expect_u32()?;
+				self.frame_mut().insert(local_def_id, value);
+				Ok(())
+			}
+		}
+	}
+
+	fn call(
+		&mut self,
+		path: &Path,
+		args: &[Id<Expr>],
+	) -> Result<Value, RuntimeError> {
+		let callee_hir = self
+			.program
+			.get(&path.module)
+			.ok_or(RuntimeError::UndefinedModule)?;
+		let body = *callee_hir
+			.map
+			.get(&path.item)
+			.ok_or(RuntimeError::UndefinedItem)?;
+		let param_ids = callee_hir
+			.params
+			.get(&path.item)
+			.ok_or(RuntimeError::UndefinedItem)?;
+
+		let mut frame = HashMap::new();
+		for (param_id, arg) in param_ids.iter().zip(args) {
+			let value = self.expr(*arg)?.expect_u32()?;
+			frame.insert(*param_id, value);
+		}
+
+		EvalCtx { program: self.program, hir: callee_hir, frames: vec![frame] }
+			.expr(body)
+	}
+
+	fn frame(&self) -> &HashMap<Id<LocalDef>, u32> {
+		self.frames.last().unwrap()
+	}
+
+	fn frame_mut(&mut self) -> &mut HashMap<Id<LocalDef>, u32> {
+		self.frames.last_mut().unwrap()
+	}
+}
+
+impl Value {
+	fn expect_u32(self) -> Result<u32, RuntimeError> {
+		match self {
+			Value::U32(n) => Ok(n),
+			Value::Void => Err(RuntimeError::VoidValue),
+		}
+	}
+}
+
+fn apply(op: BinaryOp, lhs: u32, rhs: u32) -> Result<u32, RuntimeError> {
+	Ok(match op {
+		BinaryOp::Add => lhs.wrapping_add(rhs),
+		BinaryOp::Sub => lhs.wrapping_sub(rhs),
+		BinaryOp::Mul => lhs.wrapping_mul(rhs),
+		BinaryOp::Div => {
+			if rhs == 0 {
+				return Err(RuntimeError::DivideByZero);
+			}
+			lhs.wrapping_div(rhs)
+		}
+		BinaryOp::Mod => {
+			if rhs == 0 {
+				return Err(RuntimeError::ModuloByZero);
+			}
+			lhs.wrapping_rem(rhs)
+		}
+		BinaryOp::BitAnd => lhs & rhs,
+		BinaryOp::BitOr => lhs | rhs,
+		BinaryOp::BitXor => lhs ^ rhs,
+		BinaryOp::Shl => lhs.wrapping_shl(rhs),
+		BinaryOp::Shr => lhs.wrapping_shr(rhs),
+		BinaryOp::Eq => (lhs == rhs) as u32,
+		BinaryOp::NEq => (lhs != rhs) as u32,
+		BinaryOp::Lt => (lhs < rhs) as u32,
+		BinaryOp::Gt => (lhs > rhs) as u32,
+		BinaryOp::LtEq => (lhs <= rhs) as u32,
+		BinaryOp::GtEq => (lhs >= rhs) as u32,
+		BinaryOp::And => ((lhs != 0) && (rhs != 0)) as u32,
+		BinaryOp::Or => ((lhs != 0) || (rhs != 0)) as u32,
+	})
+}
@@ -1,7 +1,15 @@
+mod codegen;
+mod eval;
+
+pub use codegen::{compile, compile_program, run, Functions, Op};
+#[cfg(any(test, feature = "disasm"))]
+pub use codegen::disassemble;
+pub use eval::{eval, Program, RuntimeError, Value};
+
 use arena::{Arena, Id};
 use cst::{CstNode, CstToken};
 use resolved_index::{Index, Item, Path, Stub, Ty};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
 use syntax::SyntaxTree;
 use text_size::TextRange;
@@ -24,18 +32,20 @@ pub fn lower(
 	};
 
 	for item in source_file.items(tree) {
-		let (name, body) = match item {
+		let (name, params, ret_ty, body) = match item {
 			cst::Item::Strukt(_) => continue,
 			cst::Item::Function(f) => match f.name(tree) {
 				Some(i) => {
+					let params = f.params(tree).collect();
+					let ret_ty = f.ret_ty(tree);
 					let body = f.body(tree).map(cst::Expr::BlockExpr);
-					(i.text(tree), body)
+					(i.text(tree), params, ret_ty, body)
 				}
 				None => continue,
 			},
 		};
 
-		ctx.function(name, body);
+		ctx.function(name, params, ret_ty, body);
 	}
 
 	(ctx.hir, ctx.errors)
@@ -47,6 +57,12 @@ pub struct Hir {
 	pub exprs: Arena<Expr>,
 	pub local_defs: Arena<LocalDef>,
 	pub tys: Arena<Ty>,
+	/// the parameter `LocalDef`s of each function, in declaration order, so
+	/// a caller can bind argument values to them
+	pub params: HashMap<String, Vec<Id<LocalDef>>>,
+	/// each function's declared return type, defaulting to `Ty::Void` for a
+	/// function with no `RetTy`
+	pub ret_tys: HashMap<String, Id<Ty>>,
 }
 
 #[derive(Clone, Copy)]
@@ -59,15 +75,17 @@ pub struct LocalDef {
 	pub value: Id<Expr>,
 }
 
+#[derive(Clone)]
 pub enum Expr {
 	Missing,
 	Integer(u32),
 	Local(Id<LocalDef>),
 	Block(Vec<Stmt>),
-	Call(Path),
+	Call(Path, Vec<Id<Expr>>),
 	Binary { lhs: Id<Expr>, rhs: Id<Expr>, op: BinaryOp },
 }
 
+#[derive(Clone, Copy)]
 pub enum BinaryOp {
 	Add,
 	Sub,
@@ -100,6 +118,7 @@ pub enum ErrorKind {
 	UndefinedItem,
 	ExpectedFunctionFoundTy,
 	TyMismatch { expected: String, actual: String },
+	ArgCountMismatch { expected: usize, actual: usize },
 }
 
 struct LowerCtx<'a> {
@@ -113,9 +132,55 @@ struct LowerCtx<'a> {
 }
 
 impl LowerCtx<'_> {
-	fn function(&mut self, name: &str, body: Option<cst::Expr>) {
+	fn function(
+		&mut self,
+		name: &str,
+		params: Vec<cst::Param>,
+		ret_ty: Option<cst::RetTy>,
+		body: Option<cst::Expr>,
+	) {
+		self.scopes.push(HashMap::new());
+
+		let ret_ty_id = match ret_ty.and_then(|r| r.name(self.tree)) {
+			Some(ty_name) => self.resolve_ty_name(&ty_name),
+			None => self.hir.tys.alloc(Ty::Void),
+		};
+
+		let mut param_ids = Vec::new();
+		for param in params {
+			let ty = match param.ty(self.tree) {
+				Some(ty_name) => self.resolve_ty_name(&ty_name),
+				None => self.hir.tys.alloc(Ty::Unknown),
+			};
+
+			// a parameter has no initializer of its own; its value is
+			// supplied by the caller, so it carries no lowered `Expr`
+			let value = self.hir.exprs.alloc(Expr::Missing);
+			let local_def_id = self.hir.local_defs.alloc(LocalDef { ty, value });
+			param_ids.push(local_def_id);
+
+			if let Some(ident) = param.name(self.tree) {
+				self.scopes
+					.last_mut()
+					.unwrap()
+					.insert(ident.text(self.tree).to_string(), local_def_id);
+			}
+		}
+
 		let (expr, _ty) = self.expr(body);
+		self.scopes.pop();
+
 		self.hir.map.insert(name.to_string(), expr);
+		self.hir.params.insert(name.to_string(), param_ids);
+		self.hir.ret_tys.insert(name.to_string(), ret_ty_id);
+	}
+
+	fn resolve_ty_name(&mut self, name: &cst::Ident) -> Id<Ty> {
+		let ty = match name.text(self.tree) {
+			"u32" => Ty::U32,
+			_ => Ty::Unknown,
+		};
+		self.hir.tys.alloc(ty)
 	}
 
 	fn stmt(&mut self, stmt: cst::Stmt) -> Stmt {
@@ -223,8 +288,10 @@ impl LowerCtx<'_> {
 			None => return (Expr::Missing, self.hir.tys.alloc(Ty::Void)),
 		};
 
-		let path = match self.path(path_cst) {
-			Some((p, Item::Function { .. })) => p,
+		let (path, params, ret_ty) = match self.path(path_cst) {
+			Some((p, Item::Function { params, ret_ty, .. })) => {
+				(p, params.clone(), ret_ty.clone())
+			}
 			Some((_, Item::Strukt { .. })) => {
 				self.errors.push(Error {
 					kind: ErrorKind::ExpectedFunctionFoundTy,
@@ -234,7 +301,29 @@ impl LowerCtx<'_> {
 			}
 			None => return (Expr::Missing, self.hir.tys.alloc(Ty::Void)),
 		};
-		(Expr::Call(path), self.hir.tys.alloc(Ty::Void))
+
+		let args: Vec<_> = call.args(self.tree).collect();
+
+		if args.len() != params.len() {
+			self.errors.push(Error {
+				kind: ErrorKind::ArgCountMismatch {
+					expected: params.len(),
+					actual: args.len(),
+				},
+				range: call.range(self.tree),
+			});
+			return (Expr::Missing, self.hir.tys.alloc(Ty::Void));
+		}
+
+		let mut arg_ids = Vec::new();
+		for (arg, expected) in args.into_iter().zip(&params) {
+			let range = arg.range(self.tree);
+			let (arg_id, actual) = self.expr(Some(arg));
+			self.expect_ty_match(expected, actual, range);
+			arg_ids.push(arg_id);
+		}
+
+		(Expr::Call(path, arg_ids), self.hir.tys.alloc(ret_ty))
 	}
 
 	fn variable_expr(
@@ -358,6 +447,232 @@ impl LowerCtx<'_> {
 	}
 }
 
+pub fn fold(hir: &mut Hir) {
+	for raw in 0..hir.exprs.len() {
+		let id = Id::from_raw(raw as u32);
+
+		let folded = match hir.exprs.get(id) {
+			Expr::Binary { lhs, rhs, op } => fold_binary(hir, *lhs, *rhs, *op),
+			_ => None,
+		};
+
+		if let Some(expr) = folded {
+			*hir.exprs.get_mut(id) = expr;
+		}
+	}
+}
+
+fn fold_binary(
+	hir: &Hir,
+	lhs: Id<Expr>,
+	rhs: Id<Expr>,
+	op: BinaryOp,
+) -> Option<Expr> {
+	let lhs = match hir.exprs.get(lhs) {
+		Expr::Integer(n) => *n,
+		_ => return None,
+	};
+	let rhs = match hir.exprs.get(rhs) {
+		Expr::Integer(n) => *n,
+		_ => return None,
+	};
+
+	let value = match op {
+		BinaryOp::Add => lhs.wrapping_add(rhs),
+		BinaryOp::Sub => lhs.wrapping_sub(rhs),
+		BinaryOp::Mul => lhs.wrapping_mul(rhs),
+		BinaryOp::Div => {
+			if rhs == 0 {
+				return None;
+			}
+			lhs.wrapping_div(rhs)
+		}
+		BinaryOp::Mod => {
+			if rhs == 0 {
+				return None;
+			}
+			lhs.wrapping_rem(rhs)
+		}
+		BinaryOp::BitAnd => lhs & rhs,
+		BinaryOp::BitOr => lhs | rhs,
+		BinaryOp::BitXor => lhs ^ rhs,
+		BinaryOp::Shl => lhs.wrapping_shl(rhs),
+		BinaryOp::Shr => lhs.wrapping_shr(rhs),
+		BinaryOp::Eq => (lhs == rhs) as u32,
+		BinaryOp::NEq => (lhs != rhs) as u32,
+		BinaryOp::Lt => (lhs < rhs) as u32,
+		BinaryOp::Gt => (lhs > rhs) as u32,
+		BinaryOp::LtEq => (lhs <= rhs) as u32,
+		BinaryOp::GtEq => (lhs >= rhs) as u32,
+		BinaryOp::And => ((lhs != 0) && (rhs != 0)) as u32,
+		BinaryOp::Or => ((lhs != 0) || (rhs != 0)) as u32,
+	};
+
+	Some(Expr::Integer(value))
+}
+
+pub fn reassociate(hir: &mut Hir) {
+	// a node reachable as the immediate lhs/rhs of another Add/Sub node is
+	// already going to be walked by that enclosing node's `collect()` call,
+	// so it isn't a maximal chain root and must be skipped below — otherwise
+	// it gets flattened and rebuilt once on its own and a second time as
+	// part of its parent's chain
+	let mut covered = HashSet::new();
+	for raw in 0..hir.exprs.len() {
+		let id = Id::from_raw(raw as u32);
+		let (lhs, rhs) = match hir.exprs.get(id) {
+			Expr::Binary { lhs, rhs, op: BinaryOp::Add | BinaryOp::Sub } => {
+				(*lhs, *rhs)
+			}
+			_ => continue,
+		};
+		for child in [lhs, rhs] {
+			if is_additive_chain(hir, child) {
+				covered.insert(child);
+			}
+		}
+	}
+
+	for raw in 0..hir.exprs.len() {
+		let id = Id::from_raw(raw as u32);
+
+		if covered.contains(&id) || !is_additive_chain(hir, id) {
+			continue;
+		}
+
+		let mut terms = AdditiveTerms::default();
+		terms.collect(hir, id, false);
+		let expr = terms.rebuild(hir);
+		*hir.exprs.get_mut(id) = expr;
+	}
+}
+
+fn is_additive_chain(hir: &Hir, id: Id<Expr>) -> bool {
+	matches!(
+		hir.exprs.get(id),
+		Expr::Binary { op: BinaryOp::Add, .. }
+			| Expr::Binary { op: BinaryOp::Sub, .. }
+	)
+}
+
+#[derive(Default)]
+struct AdditiveTerms {
+	const_sum: i64,
+	local_order: Vec<Id<LocalDef>>,
+	local_coeffs: HashMap<Id<LocalDef>, i64>,
+	opaque: Vec<(Id<Expr>, bool)>,
+}
+
+impl AdditiveTerms {
+	fn collect(&mut self, hir: &Hir, id: Id<Expr>, negate: bool) {
+		let sign = if negate { -1 } else { 1 };
+
+		match hir.exprs.get(id) {
+			Expr::Integer(n) => self.const_sum += sign * i64::from(*n),
+
+			Expr::Local(local_def_id) => {
+				self.add_local(*local_def_id, sign);
+			}
+
+			Expr::Binary { lhs, rhs, op: BinaryOp::Add } => {
+				self.collect(hir, *lhs, negate);
+				self.collect(hir, *rhs, negate);
+			}
+
+			Expr::Binary { lhs, rhs, op: BinaryOp::Sub } => {
+				self.collect(hir, *lhs, negate);
+				self.collect(hir, *rhs, !negate);
+			}
+
+			Expr::Binary { lhs, rhs, op: BinaryOp::Mul } => {
+				match (hir.exprs.get(*lhs), hir.exprs.get(*rhs)) {
+					(Expr::Integer(c), Expr::Local(local_def_id))
+					| (Expr::Local(local_def_id), Expr::Integer(c)) => {
+						self.add_local(*local_def_id, sign * i64::from(*c));
+					}
+					_ => self.opaque.push((id, negate)),
+				}
+			}
+
+			_ => self.opaque.push((id, negate)),
+		}
+	}
+
+	fn add_local(&mut self, local_def_id: Id<LocalDef>, coeff: i64) {
+		if !self.local_coeffs.contains_key(&local_def_id) {
+			self.local_order.push(local_def_id);
+		}
+		*self.local_coeffs.entry(local_def_id).or_insert(0) += coeff;
+	}
+
+	fn rebuild(self, hir: &mut Hir) -> Expr {
+		let mut acc: Option<Id<Expr>> = None;
+
+		for local_def_id in self.local_order {
+			let coeff = self.local_coeffs[&local_def_id];
+			let negate = coeff < 0;
+			let magnitude = (coeff.unsigned_abs() % (1u64 << 32)) as u32;
+			if magnitude == 0 {
+				continue;
+			}
+
+			let term = hir.exprs.alloc(Expr::Local(local_def_id));
+			let term = if magnitude == 1 {
+				term
+			} else {
+				let count = hir.exprs.alloc(Expr::Integer(magnitude));
+				hir.exprs.alloc(Expr::Binary {
+					lhs: term,
+					rhs: count,
+					op: BinaryOp::Mul,
+				})
+			};
+
+			acc = Some(push_term(hir, acc, term, negate));
+		}
+
+		for (term, negate) in self.opaque {
+			acc = Some(push_term(hir, acc, term, negate));
+		}
+
+		let const_negate = self.const_sum < 0;
+		let const_magnitude =
+			(self.const_sum.unsigned_abs() % (1u64 << 32)) as u32;
+		if const_magnitude != 0 {
+			let term = hir.exprs.alloc(Expr::Integer(const_magnitude));
+			acc = Some(push_term(hir, acc, term, const_negate));
+		}
+
+		match acc {
+			Some(id) => hir.exprs.get(id).clone(),
+			None => Expr::Integer(0),
+		}
+	}
+}
+
+fn push_term(
+	hir: &mut Hir,
+	acc: Option<Id<Expr>>,
+	term: Id<Expr>,
+	negate: bool,
+) -> Id<Expr> {
+	match acc {
+		None if negate => {
+			let zero = hir.exprs.alloc(Expr::Integer(0));
+			hir.exprs.alloc(Expr::Binary {
+				lhs: zero,
+				rhs: term,
+				op: BinaryOp::Sub,
+			})
+		}
+		None => term,
+		Some(acc) => {
+			let op = if negate { BinaryOp::Sub } else { BinaryOp::Add };
+			hir.exprs.alloc(Expr::Binary { lhs: acc, rhs: term, op })
+		}
+	}
+}
+
 pub fn pretty_print(hir: &Hir) -> String {
 	let mut ctx =
 		PrettyPrintCtx { hir, output: String::new(), indentation: 0 };
@@ -413,8 +728,15 @@ impl PrettyPrintCtx<'_> {
 				write!(self.output, "l{}", local_def_id.to_raw()).unwrap()
 			}
 			Expr::Block(stmts) => self.block_expr(stmts),
-			Expr::Call(path) => {
-				write!(self.output, "{}.{}()", path.module, path.item).unwrap()
+			Expr::Call(path, args) => {
+				write!(self.output, "{}.{}(", path.module, path.item).unwrap();
+				for (i, arg) in args.iter().enumerate() {
+					if i != 0 {
+						self.output.push_str(", ");
+					}
+					self.expr(*arg);
+				}
+				self.output.push(')');
 			}
 			Expr::Binary { lhs, rhs, op } => {
 				self.expr(*lhs);
@@ -502,26 +824,32 @@ fn run_tests() {
 			resolved_index.stubs.insert(file_name.clone(), resolved_stub);
 		}
 
-		let mut hirs = HashMap::new();
+		let mut hirs: Program = HashMap::new();
 		let mut errors = HashMap::new();
 		for (file_name, resolved_stub) in &resolved_index.stubs {
 			let (source_file, tree) = &syntax_trees[file_name];
-			let (hir, e) = lower(
+			let (mut hir, e) = lower(
 				resolved_stub,
 				file_name,
 				&resolved_index,
 				*source_file,
 				tree,
 			);
-			hirs.insert(file_name, hir);
+			fold(&mut hir);
+			reassociate(&mut hir);
+			hirs.insert(file_name.clone(), hir);
 			errors.insert(file_name, e);
 		}
 
+		let functions = compile_program(&hirs);
+
 		let mut output = String::new();
 
-		let mut hirs: Vec<_> = hirs.into_iter().collect();
-		hirs.sort_by_key(|(name, _)| *name);
-		for (i, (file_name, hir)) in hirs.iter().enumerate() {
+		let mut file_names: Vec<_> = hirs.keys().cloned().collect();
+		file_names.sort();
+		for (i, file_name) in file_names.iter().enumerate() {
+			let hir = &hirs[file_name];
+
 			if i != 0 {
 				output.push_str("\n\n");
 			}
@@ -529,6 +857,36 @@ fn run_tests() {
 			output.push_str(file_name);
 			output.push_str(" ==\n");
 			output.push_str(&pretty_print(hir));
+
+			// a module's `main` function, if it has one, is run so eval
+			// gets exercised by the same fixtures as the other passes
+			if hir.map.contains_key("main") {
+				output.push('\n');
+				match eval(&hirs, file_name, "main") {
+					Ok(value) => write!(output, "main() = {value:?}").unwrap(),
+					Err(e) => write!(output, "main() errored: {e:?}").unwrap(),
+				}
+
+				let bytes = compile(hir, "main").expect("main came from hir.map");
+				output.push_str("\n\n");
+				output.push_str(disassemble(&bytes).trim_end());
+
+				// `run_bytes` has no notion of a `Missing` expression: a
+				// `Missing` argument compiles to zero pushed bytes, which
+				// would desync the caller's expected stack depth, so only
+				// run bytecode for a module that lowered without errors
+				if errors[file_name].is_empty() {
+					output.push_str("\n\n");
+					match run(&functions, file_name, "main") {
+						Ok(value) => {
+							write!(output, "run(main) = {value:?}").unwrap()
+						}
+						Err(e) => {
+							write!(output, "run(main) errored: {e:?}").unwrap()
+						}
+					}
+				}
+			}
 		}
 
 		if errors.values().all(|e| e.is_empty()) {
@@ -564,6 +922,13 @@ fn run_tests() {
 						"expected type `{expected}`, found type `{actual}`"
 					)
 					.unwrap(),
+					ErrorKind::ArgCountMismatch { expected, actual } => {
+						write!(
+							output,
+							"expected {expected} argument(s), found {actual}"
+						)
+						.unwrap()
+					}
 				}
 			}
 		}
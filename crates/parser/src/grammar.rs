@@ -14,11 +14,181 @@ pub(crate) fn source_file(p: &mut Parser) {
 fn item(p: &mut Parser) {
 	match p.peek() {
 		Some(TokenKind::StructKw) => strukt(p),
+		Some(TokenKind::FnKw) => function(p),
 		Some(_) => p.error_without_recovery("item"),
 		None => unreachable!(),
 	}
 }
 
+fn function(p: &mut Parser) {
+	p.start_node(NodeKind::Function);
+	p.bump(TokenKind::FnKw);
+	p.expect_with_name(TokenKind::Ident, "function name");
+
+	p.expect(TokenKind::LParen);
+	while !p.at_recovery() && !p.at(TokenKind::RParen) {
+		p.start_node(NodeKind::Param);
+		p.expect_with_name(TokenKind::Ident, "parameter name");
+		p.expect_with_name(TokenKind::Ident, "type");
+		p.finish_node();
+
+		if p.at(TokenKind::Comma) {
+			p.bump(TokenKind::Comma);
+		}
+	}
+	p.expect(TokenKind::RParen);
+
+	if p.at(TokenKind::Ident) {
+		p.start_node(NodeKind::RetTy);
+		p.bump(TokenKind::Ident);
+		p.finish_node();
+	}
+
+	block_expr(p);
+	p.finish_node();
+}
+
+fn block_expr(p: &mut Parser) {
+	p.start_node(NodeKind::BlockExpr);
+	p.expect(TokenKind::LBrace);
+
+	while !p.at_recovery() && !p.at(TokenKind::RBrace) {
+		stmt(p);
+	}
+
+	p.expect(TokenKind::RBrace);
+	p.finish_node();
+}
+
+fn stmt(p: &mut Parser) {
+	match p.peek() {
+		Some(TokenKind::VarKw) => var_stmt(p),
+		Some(_) => p.error_without_recovery("statement"),
+		None => unreachable!(),
+	}
+}
+
+fn var_stmt(p: &mut Parser) {
+	p.start_node(NodeKind::VarStmt);
+	p.bump(TokenKind::VarKw);
+	p.expect_with_name(TokenKind::Ident, "variable name");
+	p.expect(TokenKind::Eq);
+	expr(p);
+	p.expect(TokenKind::Semicolon);
+	p.finish_node();
+}
+
+fn expr(p: &mut Parser) {
+	expr_bp(p, 0);
+}
+
+// precedence climbing: `lhs` is parsed eagerly, then we repeatedly look for
+// an operator whose left binding power is high enough to bind to what's been
+// parsed so far, wrapping it (and everything parsed under `right_bp`) as a
+// `BinaryExpr` after the fact via the checkpoint taken before `lhs`
+fn expr_bp(p: &mut Parser, min_bp: u8) {
+	let checkpoint = p.checkpoint();
+	lhs(p);
+
+	loop {
+		let kind = match p.peek() {
+			Some(kind) => kind,
+			None => break,
+		};
+
+		let (left_bp, right_bp) = match infix_binding_power(kind) {
+			Some(bp) => bp,
+			None => break,
+		};
+		if left_bp < min_bp {
+			break;
+		}
+
+		p.start_node_at(checkpoint, NodeKind::BinaryExpr);
+		p.bump_any();
+		expr_bp(p, right_bp);
+		p.finish_node();
+	}
+}
+
+fn infix_binding_power(kind: TokenKind) -> Option<(u8, u8)> {
+	Some(match kind {
+		TokenKind::PipePipe => (1, 2),
+		TokenKind::AndAnd => (3, 4),
+		TokenKind::EqEq | TokenKind::BangEq => (5, 6),
+		TokenKind::Lt
+		| TokenKind::Gt
+		| TokenKind::LtEq
+		| TokenKind::GtEq => (7, 8),
+		TokenKind::Pipe => (9, 10),
+		TokenKind::Caret => (11, 12),
+		TokenKind::And => (13, 14),
+		TokenKind::LtLt | TokenKind::GtGt => (15, 16),
+		TokenKind::Plus | TokenKind::Hyphen => (17, 18),
+		TokenKind::Star | TokenKind::Slash | TokenKind::Percent => (19, 20),
+		_ => return None,
+	})
+}
+
+fn lhs(p: &mut Parser) {
+	match p.peek() {
+		Some(TokenKind::Integer) => integer_expr(p),
+		Some(TokenKind::Ident) => path_or_variable_expr(p),
+		Some(_) => p.error_without_recovery("expression"),
+		None => unreachable!(),
+	}
+}
+
+fn integer_expr(p: &mut Parser) {
+	p.start_node(NodeKind::IntegerExpr);
+	p.bump(TokenKind::Integer);
+	p.finish_node();
+}
+
+// an identifier starts either a bare variable reference or, when followed by
+// `(` (optionally after a `.item` segment), a call of a local or foreign
+// path; we don't know which until we've looked past it, so the path/variable
+// node is wrapped on after the fact from a checkpoint
+fn path_or_variable_expr(p: &mut Parser) {
+	let checkpoint = p.checkpoint();
+	p.bump(TokenKind::Ident);
+
+	let is_foreign = p.at(TokenKind::Dot);
+	if is_foreign {
+		p.bump(TokenKind::Dot);
+		p.expect_with_name(TokenKind::Ident, "item name");
+	}
+
+	if p.at(TokenKind::LParen) {
+		let path_kind = if is_foreign {
+			NodeKind::ForeignPath
+		} else {
+			NodeKind::LocalPath
+		};
+		p.start_node_at(checkpoint, path_kind);
+		p.finish_node();
+
+		p.start_node_at(checkpoint, NodeKind::CallExpr);
+		call_args(p);
+		p.finish_node();
+	} else {
+		p.start_node_at(checkpoint, NodeKind::VariableExpr);
+		p.finish_node();
+	}
+}
+
+fn call_args(p: &mut Parser) {
+	p.expect(TokenKind::LParen);
+	while !p.at_recovery() && !p.at(TokenKind::RParen) {
+		expr(p);
+
+		if p.at(TokenKind::Comma) {
+			p.bump(TokenKind::Comma);
+		}
+	}
+	p.expect(TokenKind::RParen);
+}
+
 fn strukt(p: &mut Parser) {
 	p.start_node(NodeKind::Strukt);
 	p.bump(TokenKind::StructKw);
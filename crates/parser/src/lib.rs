@@ -104,13 +104,23 @@ impl Parser {
 		self.eof()
 			|| self.at(TokenKind::LBrace)
 			|| self.at(TokenKind::RBrace)
+			|| self.at(TokenKind::RParen)
 			|| self.at(TokenKind::StructKw)
+			|| self.at(TokenKind::FnKw)
 	}
 
 	fn start_node(&mut self, kind: NodeKind) {
 		self.events.push(Event::StartNode(kind));
 	}
 
+	fn checkpoint(&self) -> usize {
+		self.events.len()
+	}
+
+	fn start_node_at(&mut self, checkpoint: usize, kind: NodeKind) {
+		self.events.insert(checkpoint, Event::StartNode(kind));
+	}
+
 	fn bump_any(&mut self) {
 		assert!(!self.eof());
 		self.events.push(Event::AddToken);